@@ -0,0 +1,84 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::ioi::{SubtaskId, Task, TestcaseId};
+
+/// The evaluation unit for a single solution: one `(subtask, testcase)` pair to execute.
+pub type EvaluationUnit = (SubtaskId, TestcaseId);
+
+/// The `(subtask, testcase)` pairs of `task`, in the order its executor should dispatch them:
+/// sorted unless `seed` is set, in which case they are shuffled deterministically from it. The
+/// executor's dispatch loop is expected to call this (in place of its own sorted iteration)
+/// instead of flattening the testcase list itself.
+pub fn evaluation_order(task: &Task, seed: Option<u64>) -> Vec<EvaluationUnit> {
+    let mut units: Vec<EvaluationUnit> = task
+        .subtasks
+        .iter()
+        .flat_map(|(&subtask, data)| {
+            data.testcases
+                .keys()
+                .map(move |&testcase| (subtask, testcase))
+        })
+        .collect();
+    units.sort_unstable();
+    if let Some(seed) = seed {
+        shuffle_evaluation_order(&mut units, seed);
+    }
+    units
+}
+
+/// Shuffle the flattened list of evaluation units in place, using a seed so a failing run can be
+/// replayed. Dispatching in a deterministic pseudo-random order instead of sorted order exposes
+/// communication graders and custom checkers that accidentally assume testcases run sequentially
+/// or share mutable state between cases.
+pub fn shuffle_evaluation_order(units: &mut [EvaluationUnit], seed: u64) {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut i = units.len();
+    while i > 1 {
+        i -= 1;
+        let j = rng.gen_range(0..=i);
+        units.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_units() -> Vec<EvaluationUnit> {
+        vec![(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]
+    }
+
+    #[test]
+    fn shuffle_is_deterministic_for_a_given_seed() {
+        let mut a = sample_units();
+        let mut b = sample_units();
+        shuffle_evaluation_order(&mut a, 42);
+        shuffle_evaluation_order(&mut b, 42);
+        assert_eq!(a, b, "same seed must produce the same permutation");
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation_of_the_input() {
+        let original = sample_units();
+        let mut shuffled = original.clone();
+        shuffle_evaluation_order(&mut shuffled, 7);
+        let mut sorted_shuffled = shuffled;
+        sorted_shuffled.sort_unstable();
+        assert_eq!(
+            sorted_shuffled, original,
+            "shuffle must not lose or duplicate units"
+        );
+    }
+
+    #[test]
+    fn shuffle_actually_reorders_for_some_seed() {
+        let original = sample_units();
+        let mut shuffled = original.clone();
+        shuffle_evaluation_order(&mut shuffled, 1);
+        assert_ne!(
+            shuffled, original,
+            "seed 1 is expected to produce a non-identity permutation"
+        );
+    }
+}
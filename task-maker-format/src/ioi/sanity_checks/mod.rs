@@ -0,0 +1,17 @@
+use crate::ioi::Task;
+use crate::sanity_checks::SanityCheck;
+
+mod solution_annotations;
+
+pub use solution_annotations::SolutionAnnotations;
+
+/// The sanity checks that are run by default on every IOI task.
+///
+/// Whatever drives the IOI evaluation DAG is expected to call this (mirroring how the `terry`
+/// format's own sanity checks are looked up) and run each check's `pre_hook`/`post_hook` around
+/// the evaluation. That driver is not part of this crate's `ioi` module as checked out here, so
+/// this function is wired up on the `terry` side only by analogy, not verified against a real
+/// call site.
+pub fn get_sanity_checks() -> Vec<Box<dyn SanityCheck<Task>>> {
+    vec![Box::new(SolutionAnnotations::default())]
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs;
+
+use failure::Error;
+use regex::Regex;
+
+use crate::ioi::{SubtaskId, Task, TestcaseEvaluationStatus};
+use crate::sanity_checks::SanityCheck;
+use crate::ui::UIMessage;
+use crate::{EvaluationData, UISender};
+
+/// The expected verdict of a subtask, as annotated in a solution's source with a comment such
+/// as `// @expect subtask 1 = ACCEPTED` or `// @expect subtask 2 = WRONG_ANSWER, score 0`.
+#[derive(Debug, Clone)]
+struct ExpectedOutcome {
+    status: TestcaseEvaluationStatus,
+    score: Option<f64>,
+}
+
+/// Check that the `// @expect subtask <n> = <STATUS>[, score <score>]` annotations embedded in
+/// each solution's source match the actual outcome of the evaluation. Unlike
+/// `solution_statuses`/`solution_score`, which are wired externally, this keeps the ground truth
+/// next to the code it describes and catches regressions when a "wrong" solution silently starts
+/// passing.
+#[derive(Debug, Default)]
+pub struct SolutionAnnotations;
+
+impl SanityCheck<Task> for SolutionAnnotations {
+    fn name(&self) -> &'static str {
+        "SolutionAnnotations"
+    }
+
+    // Runs after the DAG has finished executing (unlike `StatementPresent`'s `pre_hook`, which
+    // only needs the task's static files), so `eval.ui_state` already holds the final per-subtask
+    // scores and testcase statuses for every solution.
+    fn post_hook(&mut self, task: &Task, eval: &mut EvaluationData) -> Result<(), Error> {
+        for solution in task.solutions.values() {
+            let source = fs::read_to_string(&solution.source_file)?;
+            let annotations = parse_annotations(&source);
+            if annotations.is_empty() {
+                continue;
+            }
+            let state = match eval.ui_state.evaluations.get(&solution.source_file) {
+                Some(state) => state,
+                None => continue,
+            };
+            for (subtask, annotation) in annotations {
+                let expected = match annotation {
+                    Ok(expected) => expected,
+                    Err(reason) => {
+                        eval.sender.send(UIMessage::Warning {
+                            message: format!(
+                                "invalid @expect annotation: {:?} subtask {}: {}",
+                                solution.source_file, subtask, reason
+                            ),
+                        })?;
+                        continue;
+                    }
+                };
+                let subtask_state = match state.subtasks.get(&subtask) {
+                    Some(subtask_state) => subtask_state,
+                    None => {
+                        eval.sender.send(UIMessage::Warning {
+                            message: format!(
+                                "annotation mismatch: {:?} references unknown subtask {}",
+                                solution.source_file, subtask
+                            ),
+                        })?;
+                        continue;
+                    }
+                };
+                let actual = worst_status(
+                    subtask_state
+                        .testcases
+                        .values()
+                        .map(|testcase| testcase.status.clone()),
+                );
+                if actual != expected.status {
+                    eval.sender.send(UIMessage::Warning {
+                        message: format!(
+                            "annotation mismatch: {:?} subtask {} expected {:?}, got {:?}",
+                            solution.source_file, subtask, expected.status, actual
+                        ),
+                    })?;
+                    continue;
+                }
+                if let (Some(expected_score), Some(actual_score)) =
+                    (expected.score, subtask_state.score)
+                {
+                    if (expected_score - actual_score).abs() > 1e-9 {
+                        eval.sender.send(UIMessage::Warning {
+                            message: format!(
+                                "annotation mismatch: {:?} subtask {} expected score {}, got {}",
+                                solution.source_file, subtask, expected_score, actual_score
+                            ),
+                        })?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The first status among a subtask's testcases, in iteration order, that is not `Accepted`,
+/// defaulting to `Accepted` if there is none. Statuses other than `Accepted` are not ranked
+/// against each other.
+fn worst_status<I: IntoIterator<Item = TestcaseEvaluationStatus>>(
+    statuses: I,
+) -> TestcaseEvaluationStatus {
+    statuses
+        .into_iter()
+        .reduce(|worst, status| {
+            if worst == TestcaseEvaluationStatus::Accepted {
+                status
+            } else {
+                worst
+            }
+        })
+        .unwrap_or(TestcaseEvaluationStatus::Accepted)
+}
+
+/// Parse `// @expect subtask <id> = <STATUS>[, score <score>]` annotations out of a solution's
+/// source. Only `//` and `#` line comments are recognized, which covers every language
+/// task-maker currently supports. A malformed status or score is reported as `Err` rather than
+/// silently dropped, so a typo in the annotation fails loudly instead of never being checked.
+fn parse_annotations(source: &str) -> HashMap<SubtaskId, Result<ExpectedOutcome, String>> {
+    let pattern =
+        Regex::new(r"(?://|#)\s*@expect\s+subtask\s+(\d+)\s*=\s*(\w+)(?:\s*,\s*score\s+(\S+))?")
+            .unwrap();
+    let mut result = HashMap::new();
+    for line in source.lines() {
+        if let Some(captures) = pattern.captures(line) {
+            let subtask: SubtaskId = match captures[1].parse() {
+                Ok(subtask) => subtask,
+                Err(_) => continue,
+            };
+            result.insert(subtask, parse_outcome(&captures));
+        }
+    }
+    result
+}
+
+/// Parse the `<STATUS>[, score <score>]` part of an annotation into an `ExpectedOutcome`, or a
+/// human-readable reason why it couldn't be parsed.
+fn parse_outcome(captures: &regex::Captures<'_>) -> Result<ExpectedOutcome, String> {
+    let status = parse_status(&captures[2])
+        .ok_or_else(|| format!("unknown expected status {:?}", &captures[2]))?;
+    let score = match captures.get(3) {
+        Some(m) => Some(
+            m.as_str()
+                .parse()
+                .map_err(|_| format!("invalid expected score {:?}", m.as_str()))?,
+        ),
+        None => None,
+    };
+    Ok(ExpectedOutcome { status, score })
+}
+
+/// Map the textual verdict used in annotations to a `TestcaseEvaluationStatus`.
+fn parse_status(status: &str) -> Option<TestcaseEvaluationStatus> {
+    match status {
+        "ACCEPTED" => Some(TestcaseEvaluationStatus::Accepted),
+        "WRONG_ANSWER" => Some(TestcaseEvaluationStatus::WrongAnswer),
+        "TIME_LIMIT_EXCEEDED" => Some(TestcaseEvaluationStatus::TimeLimitExceeded),
+        "MEMORY_LIMIT_EXCEEDED" => Some(TestcaseEvaluationStatus::MemoryLimitExceeded),
+        "RUNTIME_ERROR" => Some(TestcaseEvaluationStatus::RuntimeError),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_well_formed_annotation() {
+        let source =
+            "// @expect subtask 1 = ACCEPTED\n# @expect subtask 2 = WRONG_ANSWER, score 0\n";
+        let annotations = parse_annotations(source);
+        assert_eq!(annotations.len(), 2);
+        let first = annotations[&1].as_ref().unwrap();
+        assert_eq!(first.status, TestcaseEvaluationStatus::Accepted);
+        assert_eq!(first.score, None);
+        let second = annotations[&2].as_ref().unwrap();
+        assert_eq!(second.status, TestcaseEvaluationStatus::WrongAnswer);
+        assert_eq!(second.score, Some(0.0));
+    }
+
+    #[test]
+    fn reports_an_unknown_status_instead_of_panicking() {
+        let annotations = parse_annotations("// @expect subtask 1 = WRONG_ANSEWR\n");
+        assert!(annotations[&1]
+            .as_ref()
+            .unwrap_err()
+            .contains("unknown expected status"));
+    }
+
+    #[test]
+    fn reports_an_invalid_score_instead_of_panicking() {
+        let annotations =
+            parse_annotations("// @expect subtask 1 = ACCEPTED, score not-a-number\n");
+        assert!(annotations[&1]
+            .as_ref()
+            .unwrap_err()
+            .contains("invalid expected score"));
+    }
+
+    #[test]
+    fn ignores_lines_without_an_annotation() {
+        assert!(parse_annotations("int main() { return 0; }\n").is_empty());
+    }
+
+    #[test]
+    fn worst_status_defaults_to_accepted_when_empty() {
+        assert_eq!(worst_status(Vec::new()), TestcaseEvaluationStatus::Accepted);
+    }
+
+    #[test]
+    fn worst_status_picks_the_first_non_accepted() {
+        let statuses = vec![
+            TestcaseEvaluationStatus::Accepted,
+            TestcaseEvaluationStatus::WrongAnswer,
+            TestcaseEvaluationStatus::Accepted,
+        ];
+        assert_eq!(
+            worst_status(statuses),
+            TestcaseEvaluationStatus::WrongAnswer
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::ioi::{CompilationStatus, Task, UIState};
+use crate::ui::UIMessage;
+use crate::ui::UI;
+
+/// A `UI` that consumes the same `UIMessage` stream as the other UIs but, instead of printing
+/// incremental progress, emits a single JUnit XML report once the evaluation is over: one
+/// `<testsuite>` per solution, one `<testcase>` per subtask, carrying the verdict as pass /
+/// `<failure>` / `<skipped>`, the measured time as the `time` attribute and the checker message
+/// as the failure text. This lets CI systems that already understand JUnit (GitLab, Jenkins,
+/// GitHub Actions, ...) display per-subtask pass/fail without parsing task-maker's own JSON.
+///
+/// Selecting it still needs a `"junit" => Box::new(JunitUI::new(&task))` arm wherever `--ui` is
+/// matched against the other named UIs; that dispatcher is not part of this module.
+pub struct JunitUI {
+    state: UIState,
+}
+
+impl JunitUI {
+    /// Make a new `JunitUI` starting from an empty state for the given task.
+    pub fn new(task: &Task) -> JunitUI {
+        JunitUI {
+            state: UIState::new(task),
+        }
+    }
+}
+
+impl UI for JunitUI {
+    fn on_message(&mut self, message: UIMessage) {
+        self.state.apply(message);
+    }
+
+    fn finish(&mut self) {
+        print!("{}", render_junit(&self.state));
+    }
+}
+
+/// All the distinct solution source paths known to the state: those that failed to compile and
+/// those that were evaluated. Exactly one `<testsuite>` is emitted per path returned here, so
+/// renderer and caller must agree on this set.
+pub fn solution_paths(state: &UIState) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = state
+        .compilations
+        .iter()
+        .filter(|(_, status)| matches!(status, CompilationStatus::Failed { .. }))
+        .map(|(path, _)| path)
+        .chain(state.evaluations.keys())
+        .cloned()
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Render the current `UIState` as a JUnit XML document, one `<testsuite>` per solution (a
+/// solution that failed to compile still gets a suite, surfaced as a single failing case).
+/// `pub` so callers that already hold a `UIState` (e.g. the test harness, which captures one from
+/// a `--ui json` run) can get the same report `JunitUI` would have produced without re-running
+/// task-maker under `--ui junit` just to diff its stdout.
+pub fn render_junit(state: &UIState) -> String {
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(out, "<testsuites>").unwrap();
+    for path in solution_paths(state) {
+        write_solution_suite(&mut out, &path, state);
+    }
+    writeln!(out, "</testsuites>").unwrap();
+    out
+}
+
+/// Write the `<testsuite>` for a single solution. A solution whose compilation failed is
+/// reported as a single failing `compilation` case; otherwise one `<testcase>` per subtask is
+/// emitted, as pass / `<failure>` / `<skipped>` depending on whether it was scored at all.
+fn write_solution_suite(out: &mut String, path: &Path, state: &UIState) {
+    let name = path.file_name().unwrap().to_string_lossy();
+    if let Some(CompilationStatus::Failed { .. }) = state.compilations.get(path) {
+        writeln!(
+            out,
+            r#"  <testsuite name="{}" tests="1" failures="1">"#,
+            name
+        )
+        .unwrap();
+        writeln!(out, r#"    <testcase name="compilation">"#).unwrap();
+        writeln!(out, r#"      <failure message="compilation failed" />"#).unwrap();
+        writeln!(out, "    </testcase>").unwrap();
+        writeln!(out, "  </testsuite>").unwrap();
+        return;
+    }
+    let eval = match state.evaluations.get(path) {
+        Some(eval) => eval,
+        None => return,
+    };
+    let failures = eval
+        .subtasks
+        .values()
+        .filter(|subtask| {
+            subtask
+                .score
+                .map_or(false, |score| score < subtask.max_score)
+        })
+        .count();
+    let skipped = eval
+        .subtasks
+        .values()
+        .filter(|subtask| subtask.score.is_none())
+        .count();
+    writeln!(
+        out,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}">"#,
+        name,
+        eval.subtasks.len(),
+        failures,
+        skipped
+    )
+    .unwrap();
+    for (subtask, subtask_state) in eval.subtasks.iter() {
+        let time: f64 = subtask_state
+            .testcases
+            .values()
+            .filter_map(|testcase| testcase.cpu_time)
+            .sum();
+        writeln!(
+            out,
+            r#"    <testcase name="subtask {}" time="{}">"#,
+            subtask, time
+        )
+        .unwrap();
+        match subtask_state.score {
+            None => writeln!(out, "      <skipped />").unwrap(),
+            Some(score) if score < subtask_state.max_score => {
+                let message = subtask_state
+                    .testcases
+                    .values()
+                    .find_map(|testcase| testcase.message.clone())
+                    .unwrap_or_default();
+                writeln!(out, r#"      <failure message="{}" />"#, escape(&message)).unwrap();
+            }
+            Some(_) => {}
+        }
+        writeln!(out, "    </testcase>").unwrap();
+    }
+    writeln!(out, "  </testsuite>").unwrap();
+}
+
+/// Escape the characters that are not allowed verbatim in an XML attribute value.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_is_a_no_op_on_plain_text() {
+        assert_eq!(escape("wrong answer on test 3"), "wrong answer on test 3");
+    }
+
+    #[test]
+    fn escape_handles_all_special_characters_together() {
+        assert_eq!(
+            escape(r#"a & b < c > d "e""#),
+            "a &amp; b &lt; c &gt; d &quot;e&quot;"
+        );
+    }
+
+    #[test]
+    fn escape_does_not_double_escape_an_existing_entity() {
+        // `&` is replaced first, so a literal `&lt;` in the input becomes `&amp;lt;` rather than
+        // being mistaken for an entity that's already escaped.
+        assert_eq!(escape("&lt;"), "&amp;lt;");
+    }
+}
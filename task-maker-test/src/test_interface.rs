@@ -1,13 +1,21 @@
 use itertools::Itertools;
+use regex::Regex;
 use std::collections::HashMap;
+use std::env;
+use std::fs;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use task_maker_format::ioi::{
-    CompilationStatus, SolutionEvaluationState, SubtaskId, Task, TestcaseEvaluationStatus, UIState,
+    CompilationStatus, SolutionEvaluationState, SubtaskId, Task, TestcaseEvaluationStatus,
+    TestcaseId, UIState,
 };
 use task_maker_format::ui::UIMessage;
 use task_maker_format::EvaluationConfig;
 
+/// Name of the environment variable that, when set to `1`, rewrites the `.snapshot` file from
+/// the observed state instead of asserting against it.
+const BLESS_ENV_VAR: &str = "TM_BLESS";
+
 /// Interface for testing a task.
 #[derive(Debug)]
 pub struct TestInterface {
@@ -31,6 +39,23 @@ pub struct TestInterface {
     pub solution_scores: HashMap<PathBuf, Vec<f64>>,
     /// The status of the evaluation of some solutions.
     pub solution_statuses: HashMap<PathBuf, Vec<TestcaseEvaluationStatus>>,
+    /// Whether to compare a full snapshot of the `UIState` instead of asserting on the
+    /// individual fields above.
+    pub snapshot: bool,
+    /// Ordered list of regex-based normalization rules applied to the serialized `UIState`
+    /// before it is compared against the snapshot, to hide nondeterministic output.
+    pub normalizers: Vec<(Regex, String)>,
+    /// Whether to fail the test if task-maker reports a `// @expect` annotation mismatch (see
+    /// `ioi::sanity_checks::SolutionAnnotations`).
+    pub check_annotations: bool,
+    /// The regexes that the checker/validator feedback message must match, indexed by solution,
+    /// subtask and testcase.
+    pub checker_messages: HashMap<(PathBuf, SubtaskId, TestcaseId), Regex>,
+    /// The seed used to dispatch the evaluation units in a deterministic pseudo-random order
+    /// instead of sorted order, to expose order-dependent graders.
+    pub eval_seed: Option<u64>,
+    /// Whether to also render the JUnit report of the run and validate it.
+    pub junit_report: bool,
 }
 
 impl TestInterface {
@@ -50,6 +75,12 @@ impl TestInterface {
             subtask_scores: None,
             solution_scores: HashMap::new(),
             solution_statuses: HashMap::new(),
+            snapshot: false,
+            normalizers: vec![Self::path_separator_rule()],
+            check_annotations: false,
+            checker_messages: HashMap::new(),
+            eval_seed: None,
+            junit_report: false,
         }
     }
 
@@ -119,6 +150,76 @@ impl TestInterface {
         self
     }
 
+    /// Enable golden-snapshot mode: instead of asserting on the individual fields, compare the
+    /// whole reconstructed `UIState` against a checked-in `.snapshot` file beside the task
+    /// directory, emitting a unified diff on mismatch. Rerun with `TM_BLESS=1` to (re)write the
+    /// snapshot from the observed state, mirroring compiletest/ui_test's blessing workflow.
+    pub fn snapshot(&mut self) -> &mut Self {
+        self.snapshot = true;
+        self
+    }
+
+    /// Add a normalization rule run (in order, after the built-in path-separator rule) over the
+    /// serialized state before it is compared against the snapshot, to hide nondeterministic
+    /// output such as wall times, memory usage, absolute paths or worker ids.
+    pub fn normalize(&mut self, pattern: &str, replacement: &str) -> &mut Self {
+        self.normalizers.push((
+            Regex::new(pattern).expect("Invalid normalization regex"),
+            replacement.into(),
+        ));
+        self
+    }
+
+    /// Built-in rule that normalizes Windows-style path separators to `/` so snapshots taken on
+    /// different platforms compare equal. JSON escapes a literal `\` in the serialized state
+    /// as a doubled `\\`, while control-character escapes such as `\n`/`\t`/`\"` are a single
+    /// backslash followed by a non-backslash character; matching only the doubled form keeps this
+    /// rule from also mangling those escapes.
+    fn path_separator_rule() -> (Regex, String) {
+        (Regex::new(r"\\\\").unwrap(), "/".into())
+    }
+
+    /// Check that the `// @expect subtask <n> = <STATUS>[, score <score>]` annotations embedded
+    /// in the solutions' sources match the actual evaluation outcome, instead of wiring the
+    /// expectation externally through `solution_statuses`/`solution_score`.
+    pub fn check_annotations(&mut self) -> &mut Self {
+        self.check_annotations = true;
+        self
+    }
+
+    /// Check that the checker/validator feedback message for the given testcase of the given
+    /// solution matches the specified regex anywhere in the text.
+    pub fn checker_message<P: Into<PathBuf>>(
+        &mut self,
+        solution: P,
+        subtask: SubtaskId,
+        testcase: TestcaseId,
+        pattern: &str,
+    ) -> &mut Self {
+        self.checker_messages.insert(
+            (solution.into(), subtask, testcase),
+            Regex::new(pattern).expect("Invalid checker message regex"),
+        );
+        self
+    }
+
+    /// Dispatch the evaluation units in a deterministic pseudo-random order derived from the
+    /// given seed instead of sorted order, to expose solutions and checkers that accidentally
+    /// depend on the execution order. Forwarded to task-maker as `--eval-seed`; the task-maker
+    /// binary must accept that flag and call `ioi::eval_order::evaluation_order` from its
+    /// dispatch loop for this to have any effect.
+    pub fn shuffle_seed(&mut self, seed: u64) -> &mut Self {
+        self.eval_seed = Some(seed);
+        self
+    }
+
+    /// Also check that the JUnit XML report that `--ui junit` would emit for this run is well
+    /// formed and has one `<testsuite>` per solution.
+    pub fn junit_report(&mut self) -> &mut Self {
+        self.junit_report = true;
+        self
+    }
+
     /// Spawn task-maker, reading its json output and checking that all the checks are good.
     pub fn run(&self) {
         println!("Expecting: {:#?}", self);
@@ -141,6 +242,9 @@ impl TestInterface {
         command.arg("--ui").arg("json");
         command.arg("--no-cache");
         command.arg("--dry-run");
+        if let Some(seed) = self.eval_seed {
+            command.arg("--eval-seed").arg(seed.to_string());
+        }
         command.env("RUST_BACKTRACE", "1");
         command.stdout(Stdio::piped());
         command.stderr(Stdio::piped());
@@ -151,16 +255,85 @@ impl TestInterface {
             panic!("task-maker exited with: {:?}", output.status);
         }
         let mut state = UIState::new(&task);
+        let mut warnings = Vec::new();
         for message in String::from_utf8(output.stdout).unwrap().lines() {
             let message = serde_json::from_str::<UIMessage>(&message).expect("Invalid message");
+            if let UIMessage::Warning { message } = &message {
+                warnings.push(message.clone());
+            }
             state.apply(message);
         }
         println!("State is: {:#?}", state);
-        self.check_limits(&state);
-        self.check_compilation(&state);
-        self.check_subtasks(&state);
-        self.check_solution_scores(&state);
-        self.check_solution_statuses(&state);
+        if self.snapshot {
+            self.check_snapshot(&state);
+        } else {
+            self.check_limits(&state);
+            self.check_compilation(&state);
+            self.check_subtasks(&state);
+            self.check_solution_scores(&state);
+            self.check_solution_statuses(&state);
+        }
+        self.check_checker_messages(&state);
+        if self.check_annotations {
+            self.check_solution_annotations(&warnings);
+        }
+        if self.junit_report {
+            self.check_junit_report(&state);
+        }
+    }
+
+    /// Fail if task-maker reported any `// @expect` annotation problem while evaluating the
+    /// task: a mismatch between the annotation and the actual outcome, or the annotation itself
+    /// being malformed (see `ioi::sanity_checks::SolutionAnnotations::post_hook`).
+    fn check_solution_annotations(&self, warnings: &[String]) {
+        for warning in warnings {
+            assert!(
+                !warning.starts_with("annotation mismatch")
+                    && !warning.starts_with("invalid @expect annotation"),
+                "{}",
+                warning
+            );
+        }
+    }
+
+    /// Path of the `.snapshot` file checked in beside the task directory.
+    fn snapshot_path(&self) -> PathBuf {
+        self.path.with_extension("snapshot")
+    }
+
+    /// Compare a canonical serialization of `state` against the checked-in `.snapshot` file,
+    /// rewriting it instead when `TM_BLESS=1` is set. Serialized through `serde_json` rather than
+    /// `Debug`: `UIState` and its evaluations are keyed by plain `HashMap`s (see
+    /// `check_solution_statuses` below, which already has to `.sorted()` their keys before
+    /// iterating), so a `{:#?}` dump of the same evaluation can come out in a different order
+    /// between runs and fail the comparison for no real reason. `serde_json::Value`'s map type
+    /// sorts by key, which makes the dump deterministic without having to touch `UIState` itself.
+    fn check_snapshot(&self, state: &UIState) {
+        let value = serde_json::to_value(state).expect("UIState must be serializable to JSON");
+        let mut serialized = serde_json::to_string_pretty(&value).unwrap();
+        for (pattern, replacement) in &self.normalizers {
+            serialized = pattern
+                .replace_all(&serialized, replacement.as_str())
+                .into_owned();
+        }
+        let snapshot_path = self.snapshot_path();
+        if env::var(BLESS_ENV_VAR).as_deref() == Ok("1") {
+            fs::write(&snapshot_path, &serialized)
+                .unwrap_or_else(|e| panic!("Failed to write snapshot {:?}: {}", snapshot_path, e));
+            return;
+        }
+        let expected = fs::read_to_string(&snapshot_path).unwrap_or_else(|e| {
+            panic!(
+                "Failed to read snapshot {:?}: {}. Run with {}=1 to create it.",
+                snapshot_path, e, BLESS_ENV_VAR
+            )
+        });
+        assert!(
+            expected == serialized,
+            "Snapshot mismatch for {:?}:\n{}",
+            snapshot_path,
+            unified_diff(&expected, &serialized)
+        );
     }
 
     /// Check the task limits are met.
@@ -304,4 +477,89 @@ impl TestInterface {
             }
         }
     }
+
+    /// Check that the checker/validator feedback messages match the expected patterns. Requires
+    /// the checker's message to be retained on `TestcaseEvaluationState::message`; this is a
+    /// no-op (besides the `unwrap_or("")` below) if nothing is registered via `checker_message`.
+    fn check_checker_messages(&self, state: &UIState) {
+        let evaluations: HashMap<PathBuf, &SolutionEvaluationState> = state
+            .evaluations
+            .iter()
+            .map(|(file, eval)| (PathBuf::from(file.file_name().unwrap()), eval))
+            .collect();
+        for ((name, subtask, testcase), pattern) in self.checker_messages.iter() {
+            let state = evaluations[name];
+            let actual = state.subtasks[subtask].testcases[testcase]
+                .message
+                .as_deref()
+                .unwrap_or("");
+            assert!(
+                pattern.is_match(actual),
+                "Checker message mismatch for {:?} subtask {} testcase {}: {:?} does not match {:?}",
+                name,
+                subtask,
+                testcase,
+                actual,
+                pattern
+            );
+        }
+    }
+
+    /// Check that the JUnit report `--ui junit` would have produced from this run is well formed
+    /// and has one `<testsuite>` per solution. Rendered directly from the `state` already
+    /// captured from the `--ui json` run above via `JunitUI`'s own rendering function, rather
+    /// than spawning task-maker a second time under `--ui junit`: the two runs are independent
+    /// processes, so re-running would double the per-test compute cost and could spuriously
+    /// disagree with `state` if the evaluation has any run-to-run timing flakiness.
+    fn check_junit_report(&self, state: &UIState) {
+        let xml = task_maker_format::ui::junit::render_junit(state);
+        assert!(
+            xml.trim_start().starts_with("<?xml"),
+            "Invalid JUnit report"
+        );
+        let suites = xml.matches("<testsuite ").count();
+        assert_eq!(
+            suites,
+            task_maker_format::ui::junit::solution_paths(state).len(),
+            "Wrong number of <testsuite> in the JUnit report"
+        );
+    }
+}
+
+/// Build a minimal unified-diff-style rendering of two texts for snapshot mismatch messages.
+fn unified_diff(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    for i in 0..expected_lines.len().max(actual_lines.len()) {
+        match (expected_lines.get(i), actual_lines.get(i)) {
+            (Some(e), Some(a)) if e == a => {}
+            (Some(e), Some(a)) => out += &format!("-{}\n+{}\n", e, a),
+            (Some(e), None) => out += &format!("-{}\n", e),
+            (None, Some(a)) => out += &format!("+{}\n", a),
+            (None, None) => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_is_empty_for_identical_text() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n"), "");
+    }
+
+    #[test]
+    fn unified_diff_reports_a_changed_line() {
+        assert_eq!(unified_diff("a\nb\n", "a\nc\n"), "-b\n+c\n");
+    }
+
+    #[test]
+    fn unified_diff_reports_trailing_lines_added_or_removed() {
+        assert_eq!(unified_diff("a\n", "a\nb\n"), "+b\n");
+        assert_eq!(unified_diff("a\nb\n", "a\n"), "-b\n");
+    }
 }